@@ -1,4 +1,6 @@
 
 //Nothing now wait for it
 
-pub mod models;
\ No newline at end of file
+pub mod models;
+pub mod reducers;
+pub mod utils;
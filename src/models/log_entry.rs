@@ -1,20 +1,40 @@
-use chrono::{DateTime, Utc, ParseError as ChronoParseError};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use serde_json::Value;
 use std::str::FromStr;
 use std::fmt;
 
+// Fields that make up an entry's canonical JSON shape; any other top-level
+// key is treated as flattened metadata.
+const CORE_FIELDS: [&str; 4] = ["timestamp", "user_id", "action", "duration"];
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct LogEntry {
     pub timestamp: DateTime<Utc>,
     pub user_id: String,
     pub action: ActionType,
     pub duration: Duration,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_metadata"
+    )]
     pub metadata: Option<serde_json::Value>,
 }
 
+// A present `"metadata": null` stays `Some(Value::Null)`; only a missing key
+// means `None`.
+fn deserialize_metadata<'de, D>(deserializer: D) -> Result<Option<Value>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Value::deserialize(deserializer).map(Some)
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum ActionType {
     Login,
     Logout,
@@ -26,6 +46,7 @@ pub enum ActionType {
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Duration(pub f64);
 
 #[derive(Error, Debug)]
@@ -36,6 +57,9 @@ pub enum LogEntryError {
     #[error("Invalid duration: must be non-negative")]
     NegativeDuration,
 
+    #[error("Invalid duration: must be finite")]
+    NonFiniteDuration,
+
     #[error("Invalid timestamp: {0}")]
     InvalidTimestamp(String),
 
@@ -69,6 +93,10 @@ impl LogEntry {
         }
 
         // Duration validation
+        if !self.duration.0.is_finite() {
+            return Err(LogEntryError::NonFiniteDuration);
+        }
+
         if self.duration.0 < 0.0 {
             return Err(LogEntryError::NegativeDuration);
         }
@@ -80,6 +108,110 @@ impl LogEntry {
         self.metadata = Some(metadata);
         self
     }
+
+    /// Converts the entry into a JSON object using its serde representation.
+    ///
+    /// With `flatten_metadata`, keys of an object `metadata` are promoted to the
+    /// top level. Keys named like a core field or `metadata` cannot be promoted
+    /// and stay nested under `metadata`, which is only dropped once every key
+    /// has moved up; empty metadata is kept as `{}`. Non-object metadata is
+    /// always kept nested, including `null`. Any entry that passes `validate`
+    /// round-trips through `from_json_value` in either form.
+    pub fn to_json_value(&self, flatten_metadata: bool) -> Value {
+        let mut value = serde_json::to_value(self).expect("LogEntry always serializes");
+
+        if let (true, Some(Value::Object(metadata)), Value::Object(object)) =
+            (flatten_metadata, &self.metadata, &mut value)
+        {
+            let mut nested = serde_json::Map::new();
+            for (key, field) in metadata {
+                if CORE_FIELDS.contains(&key.as_str()) || key == "metadata" {
+                    nested.insert(key.clone(), field.clone());
+                } else {
+                    object.insert(key.clone(), field.clone());
+                }
+            }
+
+            if nested.is_empty() && !metadata.is_empty() {
+                object.remove("metadata");
+            } else {
+                object.insert("metadata".to_string(), Value::Object(nested));
+            }
+        }
+
+        value
+    }
+
+    /// Returns a key identifying the entry's content, ignoring its timestamp.
+    ///
+    /// Entries with the same user, action, duration and metadata share a key
+    /// even when logged at different times; the derived `PartialEq` still
+    /// compares every field. Object keys are sorted and `-0.0` is written as
+    /// `0.0`, so entries that compare equal always get the same key.
+    ///
+    /// `validate` rejects non-finite durations, but an entry built directly
+    /// can still hold one; it is keyed as the string `NaN`, `inf` or `-inf`
+    /// rather than the `null` serde_json would write for all three.
+    pub fn content_key(&self) -> String {
+        let mut value = canonical_json(&self.to_json_value(false));
+        if let Value::Object(object) = &mut value {
+            object.remove("timestamp");
+            if !self.duration.0.is_finite() {
+                object.insert("duration".to_string(), Value::from(self.duration.0.to_string()));
+            }
+        }
+        value.to_string()
+    }
+
+    /// Builds an entry from the shape produced by [`LogEntry::to_json_value`].
+    ///
+    /// Accepts both nested and flattened metadata: any top-level key other than
+    /// the core fields and `metadata` is merged into the metadata object.
+    pub fn from_json_value(value: Value) -> Result<Self, LogEntryError> {
+        let Value::Object(mut object) = value else {
+            return Err(LogEntryError::ParseError("Expected a JSON object".to_string()));
+        };
+
+        let extra_keys: Vec<String> = object
+            .keys()
+            .filter(|key| !CORE_FIELDS.contains(&key.as_str()) && key.as_str() != "metadata")
+            .cloned()
+            .collect();
+
+        if !extra_keys.is_empty() {
+            let mut metadata = match object.remove("metadata") {
+                Some(Value::Object(metadata)) => metadata,
+                None | Some(Value::Null) => serde_json::Map::new(),
+                Some(_) => {
+                    return Err(LogEntryError::ParseError(
+                        "Metadata must be an object when flattened".to_string(),
+                    ))
+                }
+            };
+
+            for key in extra_keys {
+                if let Some(field) = object.remove(&key) {
+                    metadata.insert(key, field);
+                }
+            }
+
+            object.insert("metadata".to_string(), Value::Object(metadata));
+        }
+
+        let entry: Self = serde_json::from_value(Value::Object(object))
+            .map_err(|e| LogEntryError::ParseError(e.to_string()))?;
+
+        entry.validate()?;
+        Ok(entry)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl LogEntry {
+    /// Returns the Draft-07 JSON Schema of the entry's serde representation.
+    pub fn json_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(LogEntry)
+    }
 }
 
 impl FromStr for LogEntry {
@@ -99,12 +231,7 @@ impl FromStr for LogEntry {
 
         let user_id = parts[1].to_string();
 
-        let action = match parts[2] {
-            "login" => ActionType::Login,
-            "logout" => ActionType::Logout,
-            "search" => ActionType::Search,
-            custom => ActionType::Custom(custom.to_string()),
-        };
+        let action = ActionType::from(parts[2]);
 
         let duration = Duration(parts[3].parse::<f64>()
             .map_err(|_| LogEntryError::NegativeDuration)?);
@@ -118,15 +245,128 @@ impl fmt::Display for LogEntry {
         write!(f, "{},{},{},{}",
             self.timestamp,
             self.user_id,
-            match &self.action {
-                ActionType::Custom(s) => s,
-                action => format!("{:?}", action).to_lowercase(),
-            },
+            self.action,
             self.duration.0
         )
     }
 }
 
+/// Classifies a free-form action name, ignoring case and surrounding
+/// whitespace. Unrecognised names become `Custom`.
+///
+/// This is not an exact inverse of `Display`: a `Custom` name that matches a
+/// built-in action in any case becomes that action (`Custom("View")` reads
+/// back as `View`), and a padded one is trimmed (`Custom(" x ")` reads back as
+/// `Custom("x")`). Such entries change action on a `Display`/`FromStr` trip.
+impl From<&str> for ActionType {
+    fn from(action: &str) -> Self {
+        let action = action.trim();
+        match action.to_lowercase().as_str() {
+            "login" => ActionType::Login,
+            "logout" => ActionType::Logout,
+            "search" => ActionType::Search,
+            "view" => ActionType::View,
+            "update" => ActionType::Update,
+            "delete" => ActionType::Delete,
+            _ => ActionType::Custom(action.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ActionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionType::Custom(s) => write!(f, "{}", s),
+            action => write!(f, "{}", format!("{:?}", action).to_lowercase()),
+        }
+    }
+}
+
+// Rebuilds a value with object keys inserted in sorted order at every level,
+// whatever serde_json's `preserve_order` setting, and with `-0.0` folded into
+// `0.0` since the two compare equal.
+fn canonical_json(value: &Value) -> Value {
+    match value {
+        Value::Object(object) => {
+            let mut keys: Vec<&String> = object.keys().collect();
+            keys.sort();
+            Value::Object(
+                keys.into_iter()
+                    .map(|key| (key.clone(), canonical_json(&object[key])))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonical_json).collect()),
+        Value::Number(n) if n.is_f64() && n.as_f64() == Some(0.0) => Value::from(0.0),
+        other => other.clone(),
+    }
+}
+
+/// Walks a dotted path such as `http.response.status` through nested metadata.
+///
+/// Each segment looks up an object key, or, when the current value is an
+/// array, a zero-based index (`tags.0`) written as plain ASCII digits with no
+/// sign or leading zero. An empty path returns `value` itself.
+/// Returns `None` as soon as a segment does not resolve.
+pub fn metadata_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    if path.is_empty() {
+        return Some(value);
+    }
+
+    path.split('.').try_fold(value, |current, segment| match current {
+        Value::Object(object) => object.get(segment),
+        Value::Array(items) => array_index(segment).and_then(|i| items.get(i)),
+        _ => None,
+    })
+}
+
+// Accepts only canonical indices: `usize::from_str` alone would also take
+// `+1` and `01`.
+fn array_index(segment: &str) -> Option<usize> {
+    if segment.is_empty()
+        || !segment.bytes().all(|b| b.is_ascii_digit())
+        || (segment.len() > 1 && segment.starts_with('0'))
+    {
+        return None;
+    }
+    segment.parse().ok()
+}
+
+/// Reads a duration from a metadata value, returning it in seconds.
+///
+/// Accepted formats:
+/// - JSON integers are milliseconds: `450` is 0.45s.
+/// - JSON floats are seconds: `1.2` is 1.2s.
+/// - Strings need a unit suffix: `"450ms"`, `"1.2s"`, `"5m"` or `"2h"`, with
+///   optional whitespace around the number.
+///
+/// Anything else, including unsuffixed strings and negative or non-finite
+/// values, yields `None`.
+pub fn parse_duration_value(value: &Value) -> Option<Duration> {
+    let seconds = match value {
+        Value::Number(n) if n.is_f64() => n.as_f64()?,
+        Value::Number(n) => n.as_f64()? / 1000.0,
+        Value::String(s) => {
+            let s = s.trim();
+            let (number, scale) = if let Some(ms) = s.strip_suffix("ms") {
+                (ms, 0.001)
+            } else if let Some(secs) = s.strip_suffix('s') {
+                (secs, 1.0)
+            } else if let Some(mins) = s.strip_suffix('m') {
+                (mins, 60.0)
+            } else if let Some(hours) = s.strip_suffix('h') {
+                (hours, 3600.0)
+            } else {
+                return None;
+            };
+            number.trim().parse::<f64>().ok()? * scale
+        }
+        _ => return None,
+    };
+
+    (seconds.is_finite() && seconds >= 0.0).then_some(Duration(seconds))
+}
+
 // Example Usage
 #[cfg(test)]
 mod tests {
@@ -185,4 +425,267 @@ mod tests {
 
         assert!(entry.metadata.is_some());
     }
+
+    #[test]
+    fn test_log_entry_json_value_round_trip() {
+        let entry = LogEntry::new(
+            Utc::now(),
+            "user123".to_string(),
+            ActionType::Custom("export".to_string()),
+            Duration(12.0)
+        )
+        .unwrap()
+        .with_metadata(json!({
+            "source": "web",
+            "user_id": "shadowed"
+        }));
+
+        let nested = entry.to_json_value(false);
+        assert_eq!(nested["metadata"]["source"], "web");
+        assert_eq!(LogEntry::from_json_value(nested).unwrap(), entry);
+
+        let flat = entry.to_json_value(true);
+        assert_eq!(flat["source"], "web");
+        assert_eq!(flat["user_id"], "user123");
+        assert_eq!(flat["metadata"], json!({ "user_id": "shadowed" }));
+        assert_eq!(LogEntry::from_json_value(flat).unwrap(), entry);
+
+        let promoted = entry.clone().with_metadata(json!({ "source": "web" }));
+        let flat = promoted.to_json_value(true);
+        assert!(flat.get("metadata").is_none());
+        assert_eq!(LogEntry::from_json_value(flat).unwrap(), promoted);
+    }
+
+    #[test]
+    fn test_log_entry_json_value_null_metadata() {
+        let entry = LogEntry::new(
+            Utc::now(),
+            "user123".to_string(),
+            ActionType::Login,
+            Duration(1.0)
+        )
+        .unwrap()
+        .with_metadata(Value::Null);
+
+        for flatten in [false, true] {
+            let value = entry.to_json_value(flatten);
+            assert_eq!(value["metadata"], Value::Null);
+            assert_eq!(LogEntry::from_json_value(value).unwrap(), entry);
+        }
+
+        let without = LogEntry { metadata: None, ..entry };
+        assert_eq!(LogEntry::from_json_value(without.to_json_value(true)).unwrap(), without);
+    }
+
+    #[test]
+    fn test_log_entry_rejects_non_finite_duration() {
+        for duration in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let entry = LogEntry::new(
+                Utc::now(),
+                "user123".to_string(),
+                ActionType::Login,
+                Duration(duration)
+            );
+            assert!(matches!(entry, Err(LogEntryError::NonFiniteDuration)));
+        }
+
+        assert!("2024-01-01T00:00:00Z,user123,login,NaN".parse::<LogEntry>().is_err());
+    }
+
+    #[test]
+    fn test_log_entry_json_value_empty_metadata() {
+        let entry = LogEntry::new(
+            Utc::now(),
+            "user123".to_string(),
+            ActionType::Login,
+            Duration(1.0)
+        )
+        .unwrap()
+        .with_metadata(json!({}));
+
+        let flat = entry.to_json_value(true);
+        assert_eq!(flat["metadata"], json!({}));
+        assert_eq!(LogEntry::from_json_value(flat).unwrap(), entry);
+    }
+
+    #[test]
+    fn test_action_type_classification() {
+        assert_eq!(ActionType::from("login"), ActionType::Login);
+        assert_eq!(ActionType::from(" VIEW "), ActionType::View);
+        assert_eq!(ActionType::from("Delete"), ActionType::Delete);
+        assert_eq!(ActionType::from("export"), ActionType::Custom("export".to_string()));
+
+        for action in [ActionType::Update, ActionType::Custom("export".to_string())] {
+            assert_eq!(ActionType::from(action.to_string().as_str()), action);
+        }
+    }
+
+    #[test]
+    fn test_action_type_reclassifies_custom_names() {
+        let view = ActionType::Custom("View".to_string());
+        assert_eq!(ActionType::from(view.to_string().as_str()), ActionType::View);
+
+        let padded = ActionType::Custom(" x ".to_string());
+        assert_eq!(
+            ActionType::from(padded.to_string().as_str()),
+            ActionType::Custom("x".to_string())
+        );
+
+        let entry = LogEntry::new(
+            Utc::now(),
+            "user123".to_string(),
+            view,
+            Duration(1.0)
+        )
+        .unwrap();
+        let reparsed: LogEntry = entry.to_string().parse().unwrap();
+        assert_eq!(reparsed.action, ActionType::View);
+    }
+
+    #[test]
+    fn test_log_entry_from_str_round_trip() {
+        let line = "2024-01-01T00:00:00Z,user123,update,2.5";
+        let entry: LogEntry = line.parse().unwrap();
+
+        assert_eq!(entry.action, ActionType::Update);
+        assert_eq!(entry.to_string().parse::<LogEntry>().unwrap(), entry);
+    }
+
+    #[test]
+    fn test_log_entry_content_key() {
+        let entry = LogEntry::new(
+            Utc::now(),
+            "user123".to_string(),
+            ActionType::Search,
+            Duration(1.5)
+        )
+        .unwrap()
+        .with_metadata(json!({ "query": "rust" }));
+
+        let mut later = entry.clone();
+        later.timestamp = entry.timestamp + chrono::Duration::minutes(5);
+        assert_ne!(entry, later);
+        assert_eq!(entry.content_key(), later.content_key());
+
+        let other = entry.clone().with_metadata(json!({ "query": "go" }));
+        assert_ne!(entry.content_key(), other.content_key());
+    }
+
+    #[test]
+    fn test_log_entry_content_key_canonical() {
+        let mut forward = serde_json::Map::new();
+        forward.insert("x".to_string(), json!(1));
+        forward.insert("y".to_string(), json!({ "b": 2, "a": -0.0 }));
+
+        let mut backward = serde_json::Map::new();
+        backward.insert("y".to_string(), json!({ "a": 0.0, "b": 2 }));
+        backward.insert("x".to_string(), json!(1));
+
+        let entry = |duration: f64, metadata: serde_json::Map<String, Value>| {
+            LogEntry::new(
+                Utc::now(),
+                "user123".to_string(),
+                ActionType::View,
+                Duration(duration)
+            )
+            .unwrap()
+            .with_metadata(Value::Object(metadata))
+        };
+
+        let first = entry(0.0, forward);
+        let second = entry(-0.0, backward);
+        assert_eq!(first.metadata, second.metadata);
+        assert_eq!(first.duration, second.duration);
+        assert_eq!(first.content_key(), second.content_key());
+    }
+
+    #[test]
+    fn test_log_entry_content_key_non_finite_duration() {
+        let entry = |duration: f64| LogEntry {
+            timestamp: Utc::now(),
+            user_id: "user123".to_string(),
+            action: ActionType::View,
+            duration: Duration(duration),
+            metadata: None,
+        };
+
+        let keys: Vec<String> = [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 0.0]
+            .into_iter()
+            .map(|duration| entry(duration).content_key())
+            .collect();
+
+        for (i, key) in keys.iter().enumerate() {
+            assert!(keys[i + 1..].iter().all(|other| other != key));
+        }
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_log_entry_json_schema() {
+        let schema = serde_json::to_value(LogEntry::json_schema()).unwrap();
+
+        assert_eq!(schema["$schema"], "http://json-schema.org/draft-07/schema#");
+        assert_eq!(schema["title"], "LogEntry");
+        assert_eq!(schema["required"], json!(["action", "duration", "timestamp", "user_id"]));
+        assert!(schema["properties"].get("metadata").is_some());
+    }
+
+    #[test]
+    fn test_log_entry_from_json_value_invalid() {
+        assert!(LogEntry::from_json_value(json!("not an object")).is_err());
+
+        let missing_duration = json!({
+            "timestamp": "2024-01-01T00:00:00Z",
+            "user_id": "user123",
+            "action": "Login"
+        });
+        assert!(LogEntry::from_json_value(missing_duration).is_err());
+
+        let empty_user = json!({
+            "timestamp": "2024-01-01T00:00:00Z",
+            "user_id": " ",
+            "action": "Login",
+            "duration": 1.0
+        });
+        assert!(matches!(
+            LogEntry::from_json_value(empty_user),
+            Err(LogEntryError::EmptyUserId)
+        ));
+    }
+
+    #[test]
+    fn test_metadata_path() {
+        let metadata = json!({
+            "http": { "response": { "status": 503 } },
+            "tags": ["web", "eu"],
+            "flat.key": true
+        });
+
+        assert_eq!(metadata_path(&metadata, "http.response.status"), Some(&json!(503)));
+        assert_eq!(metadata_path(&metadata, "tags.1"), Some(&json!("eu")));
+        assert_eq!(metadata_path(&metadata, ""), Some(&metadata));
+        assert_eq!(metadata_path(&metadata, "tags.2"), None);
+        assert_eq!(metadata_path(&metadata, "tags.first"), None);
+        assert_eq!(metadata_path(&metadata, "tags.0"), Some(&json!("web")));
+        assert_eq!(metadata_path(&metadata, "tags.+1"), None);
+        assert_eq!(metadata_path(&metadata, "tags.01"), None);
+        assert_eq!(metadata_path(&metadata, "http.response.status.code"), None);
+        assert_eq!(metadata_path(&metadata, "flat.key"), None);
+    }
+
+    #[test]
+    fn test_parse_duration_value() {
+        assert_eq!(parse_duration_value(&json!(450)), Some(Duration(0.45)));
+        assert_eq!(parse_duration_value(&json!(1.2)), Some(Duration(1.2)));
+        assert_eq!(parse_duration_value(&json!("450ms")), Some(Duration(0.45)));
+        assert_eq!(parse_duration_value(&json!("1.2s")), Some(Duration(1.2)));
+        assert_eq!(parse_duration_value(&json!(" 5 m")), Some(Duration(300.0)));
+        assert_eq!(parse_duration_value(&json!("2h")), Some(Duration(7200.0)));
+
+        assert_eq!(parse_duration_value(&json!("450")), None);
+        assert_eq!(parse_duration_value(&json!("fast")), None);
+        assert_eq!(parse_duration_value(&json!(-5)), None);
+        assert_eq!(parse_duration_value(&json!("-1s")), None);
+        assert_eq!(parse_duration_value(&json!(null)), None);
+    }
 }
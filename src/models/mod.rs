@@ -1,2 +1,3 @@
+pub mod log_entry;
 
-mod log_entry;
\ No newline at end of file
+pub use log_entry::{ActionType, Duration, LogEntry, LogEntryError, metadata_path, parse_duration_value};
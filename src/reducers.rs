@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use crate::models::log_entry::LogEntry;
+
+/// Sums entry durations per action, keyed by the action's display name.
+///
+/// Buckets are keyed by name on purpose: a `Custom` action spelled like a
+/// built-in one (`Custom("login")`) shares the `login` bucket, matching how
+/// `ActionType::from` reads that name back.
+pub fn fold_summaries(entries: &[LogEntry]) -> HashMap<String, f64> {
+    entries.iter().fold(HashMap::new(), |mut acc, entry| {
+        *acc.entry(entry.action.to_string()).or_insert(0.0) += entry.duration.0;
+        acc
+    })
+}
+
+/// Counts entries per action, keyed by the action's display name.
+///
+/// Like [`fold_summaries`], a `Custom` action spelled like a built-in one
+/// shares that action's bucket.
+pub fn fold_counts(entries: &[LogEntry]) -> HashMap<String, usize> {
+    entries.iter().fold(HashMap::new(), |mut acc, entry| {
+        *acc.entry(entry.action.to_string()).or_insert(0) += 1;
+        acc
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::log_entry::{ActionType, Duration};
+    use chrono::Utc;
+
+    fn entry(action: ActionType, duration: f64) -> LogEntry {
+        LogEntry::new(Utc::now(), "user123".to_string(), action, Duration(duration)).unwrap()
+    }
+
+    #[test]
+    fn test_fold_summaries() {
+        let entries = vec![
+            entry(ActionType::Login, 10.0),
+            entry(ActionType::Login, 5.5),
+            entry(ActionType::Custom("export".to_string()), 2.0),
+        ];
+
+        let summaries = fold_summaries(&entries);
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries["login"], 15.5);
+        assert_eq!(summaries["export"], 2.0);
+    }
+
+    #[test]
+    fn test_fold_counts() {
+        let entries = vec![
+            entry(ActionType::Search, 1.0),
+            entry(ActionType::Search, 1.0),
+            entry(ActionType::Logout, 1.0),
+        ];
+
+        let counts = fold_counts(&entries);
+        assert_eq!(counts["search"], 2);
+        assert_eq!(counts["logout"], 1);
+        assert!(fold_counts(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_fold_merges_custom_with_builtin_name() {
+        let entries = vec![
+            entry(ActionType::Login, 1.0),
+            entry(ActionType::Custom("login".to_string()), 2.0),
+        ];
+
+        assert_eq!(fold_counts(&entries), HashMap::from([("login".to_string(), 2)]));
+        assert_eq!(fold_summaries(&entries), HashMap::from([("login".to_string(), 3.0)]));
+    }
+}
@@ -0,0 +1,120 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum TimeParseError {
+    #[error("Invalid duration: {0}")]
+    InvalidDuration(String),
+
+    #[error("Invalid time range: {0}")]
+    InvalidRange(String),
+}
+
+/// Parses a duration such as `90s`, `30m`, `2h` or `1d`.
+///
+/// The amount must be a whole, non-negative number directly followed by one
+/// of the lowercase units `s`, `m`, `h` or `d`.
+pub fn parse_duration(s: &str) -> Result<Duration, TimeParseError> {
+    let s = s.trim();
+    let invalid = || TimeParseError::InvalidDuration(s.to_string());
+
+    let split = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let (amount, unit) = s.split_at(split);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+
+    let duration = match unit {
+        "s" => Duration::try_seconds(amount),
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        _ => None,
+    };
+
+    duration.ok_or_else(invalid)
+}
+
+/// Parses a `start..end` range into two UTC timestamps.
+///
+/// Each bound is an RFC 3339 timestamp, a `YYYY-MM-DD` date (midnight UTC),
+/// `now`, or a duration before now such as `-2h`. The start must not be after
+/// the end.
+pub fn parse_time_range(s: &str) -> Result<(DateTime<Utc>, DateTime<Utc>), TimeParseError> {
+    parse_time_range_at(s, Utc::now())
+}
+
+fn parse_time_range_at(
+    s: &str,
+    now: DateTime<Utc>,
+) -> Result<(DateTime<Utc>, DateTime<Utc>), TimeParseError> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| TimeParseError::InvalidRange(format!("expected start..end, got {}", s)))?;
+
+    let start = parse_time_bound(start, now)?;
+    let end = parse_time_bound(end, now)?;
+
+    if start > end {
+        return Err(TimeParseError::InvalidRange(format!("{} is after {}", start, end)));
+    }
+
+    Ok((start, end))
+}
+
+fn parse_time_bound(s: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, TimeParseError> {
+    let s = s.trim();
+
+    if s == "now" {
+        return Ok(now);
+    }
+
+    if let Some(ago) = s.strip_prefix('-') {
+        return now
+            .checked_sub_signed(parse_duration(ago)?)
+            .ok_or_else(|| TimeParseError::InvalidRange(format!("{} is out of range", s)));
+    }
+
+    if let Ok(timestamp) = s.parse::<DateTime<Utc>>() {
+        return Ok(timestamp);
+    }
+
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|date| date.and_time(NaiveTime::MIN).and_utc())
+        .map_err(|_| TimeParseError::InvalidRange(format!("invalid bound: {}", s)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90s"), Ok(Duration::seconds(90)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::minutes(30)));
+        assert_eq!(parse_duration(" 2h "), Ok(Duration::hours(2)));
+        assert_eq!(parse_duration("1d"), Ok(Duration::days(1)));
+
+        for invalid in ["", "h", "90", "1.5h", "-2h", "2H", "2 h", "3w"] {
+            assert!(parse_duration(invalid).is_err(), "{:?} should not parse", invalid);
+        }
+    }
+
+    #[test]
+    fn test_parse_time_range() {
+        let now = "2023-01-02T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let (start, end) = parse_time_range_at("2023-01-01..2023-01-02", now).unwrap();
+        assert_eq!(start, "2023-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        assert_eq!(end, "2023-01-02T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+
+        let (start, end) = parse_time_range_at("-2h..now", now).unwrap();
+        assert_eq!(start, now - Duration::hours(2));
+        assert_eq!(end, now);
+
+        let (start, _) = parse_time_range_at("2023-01-02T06:30:00Z..now", now).unwrap();
+        assert_eq!(start, "2023-01-02T06:30:00Z".parse::<DateTime<Utc>>().unwrap());
+
+        assert!(parse_time_range_at("now..-1h", now).is_err());
+        assert!(parse_time_range_at("2023-01-01", now).is_err());
+        assert!(parse_time_range_at("yesterday..now", now).is_err());
+    }
+}